@@ -0,0 +1,84 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// parses "+09:00", "-05:30" or "+9" into minutes east of UTC
+pub fn parse_utc_offset(offset: &str) -> Option<i64> {
+    let (sign, rest) = match offset.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, offset.strip_prefix('+').unwrap_or(offset)),
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    // only plain digits, as integer parsing would also take a second sign
+    let number = |s: &str| -> Option<u32> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok()
+    };
+    let (hours, minutes) = (number(hours)?, number(minutes)?);
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    Some(sign * i64::from(hours * 60 + minutes))
+}
+
+pub fn format_hh_mm(timestamp: u64, utc_offset_minutes: i64) -> String {
+    let local_minutes = (timestamp as i64 / 60 + utc_offset_minutes).rem_euclid(24 * 60);
+    format!("{:02}:{:02}", local_minutes / 60, local_minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_utc_offsets() {
+        let cases = [
+            ("+09:00", Some(540)),
+            ("-05:30", Some(-330)),
+            ("+9", Some(540)),
+            ("9", Some(540)),
+            ("-0", Some(0)),
+            ("+14:00", Some(840)),
+            ("+15", None),
+            ("+05:60", None),
+            ("--5", None),
+            ("+-5", None),
+            ("+09:-5", None),
+            ("+09:+5", None),
+            ("+", None),
+            ("+9:", None),
+            (":30", None),
+            ("", None),
+            ("UTC", None),
+        ];
+        for (offset, expected) in cases {
+            assert_eq!(parse_utc_offset(offset), expected, "{:?}", offset);
+        }
+    }
+
+    #[test]
+    fn formats_local_time() {
+        // 2021-01-01T23:30:00Z
+        let timestamp = 1_609_543_800;
+        let cases = [
+            (0, "23:30"),
+            (30, "00:00"),
+            (540, "08:30"),
+            (-330, "18:00"),
+            (-24 * 60, "23:30"),
+        ];
+        for (offset, expected) in cases {
+            assert_eq!(format_hh_mm(timestamp, offset), expected, "{}", offset);
+        }
+        // negative offsets wrap back past midnight
+        assert_eq!(format_hh_mm(60, -120), "22:01");
+        assert_eq!(format_hh_mm(0, -1), "23:59");
+    }
+}
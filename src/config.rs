@@ -0,0 +1,432 @@
+use std::collections::BTreeMap;
+
+use zellij_tile::prelude::*;
+
+use crate::clock;
+use crate::shortcut::ShortcutButton;
+
+const DEFAULT_FRAME_LOG_MAX_SIZE: u64 = 1024 * 1024;
+const DEFAULT_TAB_NAME_FORMAT: &str = "{name}{sync}";
+
+#[derive(Debug)]
+pub struct Config {
+    pub palette: StatusPalette,
+    pub frame_log: bool,
+    pub frame_log_max_size: u64,
+    pub tab_colors: TabColors,
+    pub show_sessions: bool,
+    pub tab_alignment: TabAlignment,
+    pub tab_name_format: String,
+    pub tab_marks: BTreeMap<String, String>,
+    pub density: Density,
+    pub transparent_background: bool,
+    pub active_tab_style: ActiveTabStyle,
+    // minutes without input before the session is shown as away, 0 disables it
+    pub away_after: u64,
+    pub utc_offset: i64,
+    pub shortcut_buttons: Vec<ShortcutButton>,
+    pub moon_phase: bool,
+    pub moon_illumination: bool,
+    pub mode_switcher: bool,
+    pub mode_switcher_next: Option<InputMode>,
+    pub tab_filter: bool,
+}
+
+// the defaults are the ones an empty plugin configuration gets
+impl Default for Config {
+    fn default() -> Self {
+        Config::new(&BTreeMap::new())
+    }
+}
+
+impl Config {
+    pub fn new(configuration: &BTreeMap<String, String>) -> Self {
+        let palette = match configuration.get("palette").map(String::as_str) {
+            None | Some("theme") => StatusPalette::Theme,
+            Some("deuteranopia") => StatusPalette::Deuteranopia,
+            Some("protanopia") => StatusPalette::Protanopia,
+            Some("tritanopia") => StatusPalette::Tritanopia,
+            Some(other) => {
+                eprintln!("Unknown palette {:?}, falling back to theme colors.", other);
+                StatusPalette::Theme
+            }
+        };
+        let frame_log = configuration
+            .get("frame_log")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        let frame_log_max_size = match configuration.get("frame_log_max_size") {
+            None => DEFAULT_FRAME_LOG_MAX_SIZE,
+            Some(size) => size.parse().unwrap_or_else(|_| {
+                eprintln!("Invalid frame_log_max_size {:?}, using 1 MiB.", size);
+                DEFAULT_FRAME_LOG_MAX_SIZE
+            }),
+        };
+        let tab_colors = TabColors {
+            hash: configuration.get("tab_colors").map(|v| v == "hash") == Some(true),
+            names: configuration
+                .iter()
+                .filter_map(|(key, value)| {
+                    let name = key.strip_prefix("tab_color_")?;
+                    match parse_color(value) {
+                        Some(color) => Some((name.to_string(), color)),
+                        None => {
+                            eprintln!("Invalid color {:?} for tab {:?}.", value, name);
+                            None
+                        }
+                    }
+                })
+                .collect(),
+        };
+        let show_sessions = configuration
+            .get("show_sessions")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        let tab_alignment = match configuration.get("tab_alignment").map(String::as_str) {
+            None | Some("left") => TabAlignment::Left,
+            Some("center") => TabAlignment::Center,
+            Some("spread") => TabAlignment::Spread,
+            Some(other) => {
+                eprintln!("Unknown tab_alignment {:?}, aligning left.", other);
+                TabAlignment::Left
+            }
+        };
+        let tab_name_format = configuration
+            .get("tab_name_format")
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_TAB_NAME_FORMAT.to_string());
+        let tab_marks = configuration
+            .iter()
+            .filter_map(|(key, value)| {
+                let name = key.strip_prefix("tab_mark_")?;
+                Some((name.to_string(), value.clone()))
+            })
+            .collect();
+        let density = match configuration.get("density").map(String::as_str) {
+            None | Some("cozy") => Density::Cozy,
+            Some("compact") => Density::Compact,
+            Some("ultra") => Density::Ultra,
+            Some(other) => {
+                eprintln!("Unknown density {:?}, using cozy.", other);
+                Density::Cozy
+            }
+        };
+        let transparent_background = configuration
+            .get("transparent_background")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        let active_tab_style = match configuration.get("active_tab_style").map(String::as_str) {
+            None | Some("background") => ActiveTabStyle::Background,
+            Some("underline") => ActiveTabStyle::Underline,
+            Some(other) => {
+                eprintln!("Unknown active_tab_style {:?}, using background.", other);
+                ActiveTabStyle::Background
+            }
+        };
+        let away_after = match configuration.get("away_after") {
+            None => 0,
+            Some(minutes) => minutes.parse().unwrap_or_else(|_| {
+                eprintln!(
+                    "Invalid away_after {:?}, disabling the away marker.",
+                    minutes
+                );
+                0
+            }),
+        };
+        let utc_offset = match configuration.get("utc_offset") {
+            None => 0,
+            Some(offset) => clock::parse_utc_offset(offset).unwrap_or_else(|| {
+                eprintln!("Invalid utc_offset {:?}, using UTC.", offset);
+                0
+            }),
+        };
+        let shortcut_buttons = configuration
+            .get("shortcut_buttons")
+            .map(|names| {
+                names
+                    .split(',')
+                    .map(str::trim)
+                    .filter_map(|name| {
+                        let button = ShortcutButton::from_name(name);
+                        if button.is_none() {
+                            eprintln!("Unknown shortcut button {:?}.", name);
+                        }
+                        button
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let moon_phase = configuration
+            .get("moon_phase")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        let moon_illumination = configuration
+            .get("moon_illumination")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        let mode_switcher = configuration
+            .get("mode_switcher")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        let mode_switcher_next = configuration.get("mode_switcher_next").and_then(|name| {
+            let mode = SWITCHABLE_MODES
+                .iter()
+                .copied()
+                .find(|m| mode_name(*m) == name.as_str());
+            if mode.is_none() {
+                eprintln!("Unknown mode_switcher_next {:?}.", name);
+            }
+            mode
+        });
+        let tab_filter = configuration
+            .get("tab_filter")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        Config {
+            palette,
+            frame_log,
+            frame_log_max_size,
+            tab_colors,
+            show_sessions,
+            tab_alignment,
+            tab_name_format,
+            tab_marks,
+            density,
+            transparent_background,
+            active_tab_style,
+            away_after,
+            utc_offset,
+            shortcut_buttons,
+            moon_phase,
+            moon_illumination,
+            mode_switcher,
+            mode_switcher_next,
+            tab_filter,
+        }
+    }
+
+    // the mode a click on the mode switcher moves to from the current one
+    pub fn next_mode(&self, current: InputMode) -> InputMode {
+        match self.mode_switcher_next {
+            Some(next) if next != current => next,
+            None if current == InputMode::Normal => InputMode::Locked,
+            _ => InputMode::Normal,
+        }
+    }
+
+    // only the events the enabled features react to, so minimal setups don't
+    // wake the plugin up needlessly
+    pub fn event_types(&self) -> Vec<EventType> {
+        let mut event_types = vec![
+            EventType::PermissionRequestResult,
+            EventType::TabUpdate,
+            EventType::ModeUpdate,
+            EventType::Mouse,
+        ];
+        if self.show_sessions {
+            event_types.push(EventType::SessionUpdate);
+        }
+        if self.tab_name_format.contains("{panes}") {
+            event_types.push(EventType::PaneUpdate);
+        }
+        if self.away_after > 0 {
+            event_types.push(EventType::InputReceived);
+            event_types.push(EventType::Timer);
+        }
+        if self.tab_filter {
+            event_types.push(EventType::Key);
+        }
+        event_types
+    }
+
+    pub fn permissions(&self) -> Vec<PermissionType> {
+        let mut permissions = vec![
+            PermissionType::ReadApplicationState,
+            PermissionType::ChangeApplicationState,
+        ];
+        if self.shortcut_buttons.contains(&ShortcutButton::NewPane) {
+            permissions.push(PermissionType::OpenTerminalsOrPlugins);
+        }
+        permissions
+    }
+}
+
+pub const SWITCHABLE_MODES: [InputMode; 9] = [
+    InputMode::Normal,
+    InputMode::Locked,
+    InputMode::Pane,
+    InputMode::Tab,
+    InputMode::Resize,
+    InputMode::Move,
+    InputMode::Scroll,
+    InputMode::Session,
+    InputMode::Tmux,
+];
+
+pub fn mode_name(mode: InputMode) -> &'static str {
+    match mode {
+        InputMode::Normal => "normal",
+        InputMode::Locked => "locked",
+        InputMode::Pane => "pane",
+        InputMode::Tab => "tab",
+        InputMode::Resize => "resize",
+        InputMode::Move => "move",
+        InputMode::Scroll => "scroll",
+        InputMode::Session => "session",
+        InputMode::Tmux => "tmux",
+        InputMode::EnterSearch | InputMode::Search => "search",
+        InputMode::RenameTab | InputMode::RenamePane => "rename",
+        InputMode::Prompt => "prompt",
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TabAlignment {
+    #[default]
+    Left,
+    Center,
+    // equal gaps between tabs, like CSS space-between
+    Spread,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ActiveTabStyle {
+    #[default]
+    Background,
+    Underline,
+}
+
+// presets trading padding and secondary details for horizontal space
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Density {
+    #[default]
+    Cozy,
+    Compact,
+    Ultra,
+}
+
+impl Density {
+    pub fn tab_padding(self) -> usize {
+        match self {
+            Density::Cozy => 1,
+            Density::Compact | Density::Ultra => 0,
+        }
+    }
+
+    pub fn prefix_text(self) -> &'static str {
+        match self {
+            Density::Cozy => " Zellij ",
+            Density::Compact => " Z ",
+            Density::Ultra => "",
+        }
+    }
+
+    pub fn shows_session_name(self) -> bool {
+        self != Density::Ultra
+    }
+
+    pub fn shows_other_clients(self) -> bool {
+        self != Density::Ultra
+    }
+}
+
+// colors used to signal state (active tab, collapsed tabs, alerts), picked from
+// the Okabe-Ito set so that the three stay distinguishable under each deficiency
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StatusPalette {
+    #[default]
+    Theme,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl StatusPalette {
+    pub fn apply(self, mut palette: Palette) -> Palette {
+        let (active, collapsed, alert) = match self {
+            StatusPalette::Theme => return palette,
+            StatusPalette::Deuteranopia | StatusPalette::Protanopia => {
+                ((0, 114, 178), (230, 159, 0), (213, 94, 0))
+            }
+            StatusPalette::Tritanopia => ((213, 94, 0), (86, 180, 233), (204, 121, 167)),
+        };
+        palette.green = PaletteColor::Rgb(active);
+        palette.orange = PaletteColor::Rgb(collapsed);
+        palette.red = PaletteColor::Rgb(alert);
+        palette
+    }
+}
+
+// accepts either "#rrggbb" or an 8-bit color index
+fn parse_color(value: &str) -> Option<PaletteColor> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some(PaletteColor::Rgb((channel(0)?, channel(2)?, channel(4)?)));
+    }
+    value.parse().ok().map(PaletteColor::EightBit)
+}
+
+#[derive(Debug, Default)]
+pub struct TabColors {
+    hash: bool,
+    names: BTreeMap<String, PaletteColor>,
+}
+
+impl TabColors {
+    // explicitly configured colors win over the hashed ones, so a few tabs can
+    // be pinned while the rest stay automatic
+    pub fn accent(&self, tab_name: &str, palette: Palette) -> Option<PaletteColor> {
+        if let Some(color) = self.names.get(tab_name) {
+            return Some(*color);
+        }
+        if !self.hash {
+            return None;
+        }
+        // green is left out as it's the active tab's background, which would
+        // hide the marker
+        let accents = [
+            palette.red,
+            palette.yellow,
+            palette.blue,
+            palette.magenta,
+            palette.cyan,
+            palette.orange,
+            palette.purple,
+            palette.pink,
+            palette.gold,
+        ];
+        // FNV-1a, stable across sessions unlike the std hasher
+        let hash = tab_name.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        });
+        Some(accents[hash as usize % accents.len()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_an_empty_configuration() {
+        let config = Config::default();
+        assert_eq!(config.tab_name_format, DEFAULT_TAB_NAME_FORMAT);
+        assert_eq!(config.frame_log_max_size, DEFAULT_FRAME_LOG_MAX_SIZE);
+        assert_eq!(config.density, Density::Cozy);
+        assert!(config.shortcut_buttons.is_empty());
+    }
+
+    #[test]
+    fn invalid_numbers_fall_back_to_defaults() {
+        let configuration = BTreeMap::from([
+            ("away_after".to_string(), "soon".to_string()),
+            ("frame_log_max_size".to_string(), "-1".to_string()),
+        ]);
+        let config = Config::new(&configuration);
+        assert_eq!(config.away_after, 0);
+        assert_eq!(config.frame_log_max_size, DEFAULT_FRAME_LOG_MAX_SIZE);
+    }
+}
@@ -0,0 +1,86 @@
+use zellij_tile::prelude::TabInfo;
+
+// matches the query's characters in order, ignoring case, and scores by how
+// spread out the match is and then by where it starts (lower is better)
+pub fn fuzzy_score(name: &str, query: &str) -> Option<(usize, usize)> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut first = None;
+    let mut last = 0;
+    let mut from = 0;
+    for q in query.to_lowercase().chars() {
+        let found = from + name.get(from..)?.iter().position(|c| *c == q)?;
+        first.get_or_insert(found);
+        last = found;
+        from = found + 1;
+    }
+    Some(first.map_or((0, 0), |first| (last - first, first)))
+}
+
+// the tab Enter jumps to, the active one while the query is still empty
+pub fn best_match<'a>(tabs: &'a [TabInfo], query: &str) -> Option<&'a TabInfo> {
+    if query.is_empty() {
+        return tabs.iter().find(|t| t.active);
+    }
+    tabs.iter()
+        .filter_map(|t| fuzzy_score(&t.name, query).map(|score| (score, t)))
+        .min_by_key(|(score, _)| *score)
+        .map(|(_, t)| t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tab(position: usize, name: &str, active: bool) -> TabInfo {
+        TabInfo {
+            position,
+            name: name.to_string(),
+            active,
+            ..TabInfo::default()
+        }
+    }
+
+    #[test]
+    fn matches_characters_in_order() {
+        assert!(fuzzy_score("editor", "edr").is_some());
+        assert!(fuzzy_score("editor", "ot").is_none());
+        assert!(fuzzy_score("editor", "editors").is_none());
+        assert!(fuzzy_score("aa", "aaa").is_none());
+    }
+
+    #[test]
+    fn ignores_case() {
+        assert_eq!(fuzzy_score("Editor", "eDI"), fuzzy_score("editor", "edi"));
+        assert!(fuzzy_score("LOGS", "logs").is_some());
+    }
+
+    #[test]
+    fn prefers_tight_then_early_matches() {
+        // spread over 2 columns beats spread over 5
+        assert!(fuzzy_score("logs", "lg") < fuzzy_score("l-o-g-s", "lg"));
+        // with the same spread, the earlier start wins
+        assert!(fuzzy_score("log", "lo") < fuzzy_score("a log", "lo"));
+        assert_eq!(fuzzy_score("a log", "lo"), Some((1, 2)));
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(fuzzy_score("editor", ""), Some((0, 0)));
+        assert_eq!(fuzzy_score("", ""), Some((0, 0)));
+    }
+
+    #[test]
+    fn best_match_picks_the_lowest_score() {
+        let tabs = [
+            tab(0, "shell", true),
+            tab(1, "l-o-g-s", false),
+            tab(2, "logs", false),
+            tab(3, "logs too", false),
+        ];
+        assert_eq!(best_match(&tabs, "lg").map(|t| t.position), Some(2));
+        assert_eq!(best_match(&tabs, "LOGS").map(|t| t.position), Some(2));
+        assert_eq!(best_match(&tabs, "zzz").map(|t| t.position), None);
+        // an empty query keeps the active tab
+        assert_eq!(best_match(&tabs, "").map(|t| t.position), Some(0));
+    }
+}
@@ -0,0 +1,70 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use crate::clock;
+
+// /data is the plugin's own storage folder
+const FRAME_LOG_PATH: &str = "/data/frames.log";
+const ROTATED_FRAME_LOG_PATH: &str = "/data/frames.log.1";
+
+pub fn strip_ansi(styled: &str) -> String {
+    let mut plain = String::with_capacity(styled.len());
+    let mut chars = styled.chars();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            plain.push(c);
+            continue;
+        }
+        // skip a CSI sequence up to and including its final byte
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('\u{40}'..='\u{7e}').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    plain
+}
+
+// appends one line per frame, moving the log aside once it grows past max_size
+// so at most two files' worth of history is kept
+pub fn append_frame(frame: &str, max_size: u64) {
+    let timestamp = clock::now();
+    if let Ok(metadata) = fs::metadata(FRAME_LOG_PATH) {
+        if metadata.len() >= max_size {
+            if let Err(e) = fs::rename(FRAME_LOG_PATH, ROTATED_FRAME_LOG_PATH) {
+                eprintln!("Could not rotate frame log: {}", e);
+            }
+        }
+    }
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(FRAME_LOG_PATH)
+        .and_then(|mut file| writeln!(file, "{} {}", timestamp, frame));
+    if let Err(e) = written {
+        eprintln!("Could not write frame log: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_sgr_sequences() {
+        assert_eq!(strip_ansi("plain"), "plain");
+        assert_eq!(strip_ansi("\u{1b}[1m\u{1b}[38;5;2mtab\u{1b}[0m"), "tab");
+        assert_eq!(
+            strip_ansi("\u{1b}[1;48;2;0;0;0m a \u{1b}[4mb\u{1b}[24m c \u{1b}[0m"),
+            " a b c "
+        );
+    }
+
+    #[test]
+    fn keeps_text_around_a_trailing_reset() {
+        assert_eq!(strip_ansi(" Zellij \u{1b}[m"), " Zellij ");
+        assert_eq!(strip_ansi("→ +3 \u{1b}[0m\u{1b}[0K"), "→ +3 ");
+    }
+}
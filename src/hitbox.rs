@@ -0,0 +1,191 @@
+use zellij_tile::prelude::InputMode;
+
+use crate::shortcut::ShortcutButton;
+use crate::LinePart;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClickTarget {
+    // tab position as reported in TabInfo, starting from 0
+    Tab(usize),
+    Session(String),
+    Shortcut(ShortcutButton),
+    // the current mode indicator, and the entries of its opened menu
+    ModeSwitcher,
+    Mode(InputMode),
+    TabFilter,
+}
+
+// a clickable column range [start, end) on one row of the rendered bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hitbox {
+    pub row: usize,
+    pub start: usize,
+    pub end: usize,
+    pub target: ClickTarget,
+}
+
+pub fn hitboxes(lines: &[Vec<LinePart>]) -> Vec<Hitbox> {
+    let mut hitboxes = vec![];
+    for (row, line) in lines.iter().enumerate() {
+        let mut start = 0;
+        for part in line {
+            let end = start + part.len;
+            let target = part.target.clone().or(part.tab_index.map(ClickTarget::Tab));
+            // zero width parts (e.g. an empty collapsed tabs message) can't be clicked
+            if let Some(target) = target.filter(|_| end > start) {
+                hitboxes.push(Hitbox {
+                    row,
+                    start,
+                    end,
+                    target,
+                });
+            }
+            start = end;
+        }
+    }
+    hitboxes
+}
+
+pub fn target_at(hitboxes: &[Hitbox], row: usize, col: usize) -> Option<&ClickTarget> {
+    hitboxes
+        .iter()
+        .find(|hitbox| hitbox.row == row && (hitbox.start..hitbox.end).contains(&col))
+        .map(|hitbox| &hitbox.target)
+}
+
+#[cfg(test)]
+mod tests {
+    use zellij_tile::prelude::*;
+
+    use super::*;
+    use crate::config::Config;
+    use crate::line::{tab_line, PrefixState};
+
+    fn part(len: usize, tab_index: Option<usize>, target: Option<ClickTarget>) -> LinePart {
+        LinePart {
+            part: "x".repeat(len),
+            len,
+            tab_index,
+            target,
+        }
+    }
+
+    fn render(all_tabs: Vec<LinePart>, active_tab_index: usize, cols: usize) -> Vec<Vec<LinePart>> {
+        tab_line(
+            None,
+            &PrefixState::default(),
+            all_tabs,
+            active_tab_index,
+            1,
+            cols,
+            Palette::default(),
+            PluginCapabilities::default(),
+            false,
+            InputMode::Normal,
+            &Config::default(),
+        )
+    }
+
+    #[test]
+    fn start_is_inclusive_and_end_exclusive() {
+        let line = vec![
+            part(3, None, None),
+            part(4, Some(0), None),
+            part(2, Some(1), None),
+        ];
+        let hitboxes = hitboxes(&[line]);
+        assert_eq!(target_at(&hitboxes, 0, 2), None);
+        assert_eq!(target_at(&hitboxes, 0, 3), Some(&ClickTarget::Tab(0)));
+        assert_eq!(target_at(&hitboxes, 0, 6), Some(&ClickTarget::Tab(0)));
+        assert_eq!(target_at(&hitboxes, 0, 7), Some(&ClickTarget::Tab(1)));
+        assert_eq!(target_at(&hitboxes, 0, 8), Some(&ClickTarget::Tab(1)));
+        assert_eq!(target_at(&hitboxes, 0, 9), None);
+    }
+
+    #[test]
+    fn zero_width_parts_get_no_hitbox() {
+        // an empty collapsed tabs message still carries a tab index
+        let line = vec![
+            part(0, Some(3), None),
+            part(5, Some(4), None),
+            part(0, Some(5), None),
+        ];
+        let hitboxes = hitboxes(&[line]);
+        assert_eq!(
+            hitboxes,
+            vec![Hitbox {
+                row: 0,
+                start: 0,
+                end: 5,
+                target: ClickTarget::Tab(4),
+            }]
+        );
+    }
+
+    #[test]
+    fn target_wins_over_tab_index() {
+        let session = ClickTarget::Session("other".to_string());
+        let line = vec![part(4, Some(2), Some(session.clone()))];
+        let hitboxes = hitboxes(&[line]);
+        assert_eq!(target_at(&hitboxes, 0, 0), Some(&session));
+    }
+
+    #[test]
+    fn other_rows_do_not_match() {
+        let lines = vec![vec![part(4, Some(0), None)], vec![part(2, None, None)]];
+        let hitboxes = hitboxes(&lines);
+        assert_eq!(target_at(&hitboxes, 0, 1), Some(&ClickTarget::Tab(0)));
+        assert_eq!(target_at(&hitboxes, 1, 1), None);
+        assert_eq!(target_at(&hitboxes, usize::MAX, 1), None);
+    }
+
+    #[test]
+    fn collapsed_markers_point_past_the_visible_tabs() {
+        // a step above 1 stands for a filtered subset, where list index and
+        // tab position differ
+        for step in [1, 3] {
+            for active_tab_index in [0, 5, 9] {
+                let all_tabs = (0..10).map(|i| part(10, Some(i * step), None)).collect();
+                let line = render(all_tabs, active_tab_index, 60).remove(0);
+                let hitboxes = hitboxes(&[line]);
+                let visible: Vec<usize> = (0..10)
+                    .filter(|i| {
+                        hitboxes
+                            .iter()
+                            .filter(|h| h.target == ClickTarget::Tab(i * step))
+                            .any(|h| h.end - h.start == 10)
+                    })
+                    .collect();
+                let (first, last) = (visible[0], visible[visible.len() - 1]);
+                assert!(visible.contains(&active_tab_index));
+                assert!(visible.len() < 10);
+                // the markers sit right before the first and right after the last
+                // visible tab, and switch to the tab hidden next to them
+                let marker_before = hitboxes.iter().find(|h| {
+                    hitboxes
+                        .iter()
+                        .any(|t| t.target == ClickTarget::Tab(first * step) && t.start == h.end)
+                });
+                let marker_after = hitboxes.iter().find(|h| {
+                    hitboxes
+                        .iter()
+                        .any(|t| t.target == ClickTarget::Tab(last * step) && t.end == h.start)
+                });
+                match first {
+                    0 => assert!(marker_before.is_none()),
+                    _ => assert_eq!(
+                        marker_before.unwrap().target,
+                        ClickTarget::Tab((first - 1) * step)
+                    ),
+                }
+                match last {
+                    9 => assert!(marker_after.is_none()),
+                    _ => assert_eq!(
+                        marker_after.unwrap().target,
+                        ClickTarget::Tab((last + 1) * step)
+                    ),
+                }
+            }
+        }
+    }
+}
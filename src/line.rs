@@ -1,6 +1,10 @@
-use ansi_term::ANSIStrings;
+use ansi_term::{ANSIStrings, Style};
 use unicode_width::UnicodeWidthStr;
 
+use crate::clock;
+use crate::config::{mode_name, Config, TabAlignment, SWITCHABLE_MODES};
+use crate::hitbox::ClickTarget;
+use crate::moon;
 use crate::{LinePart, ARROW_SEPARATOR};
 use zellij_tile::prelude::*;
 use zellij_tile_utils::style;
@@ -11,6 +15,7 @@ fn get_current_title_len(current_title: &[LinePart]) -> usize {
 
 // move elements from before_active and after_active into tabs_to_render while they fit in cols
 // adds collapsed_tabs to the left and right if there's left over tabs that don't fit
+#[allow(clippy::too_many_arguments)]
 fn populate_tabs_in_tab_line(
     tabs_before_active: &mut Vec<LinePart>,
     tabs_after_active: &mut Vec<LinePart>,
@@ -19,6 +24,7 @@ fn populate_tabs_in_tab_line(
     palette: Palette,
     capabilities: PluginCapabilities,
     mode: InputMode,
+    transparent: bool,
 ) {
     let mut middle_size = get_current_title_len(tabs_to_render);
 
@@ -28,24 +34,27 @@ fn populate_tabs_in_tab_line(
         let left_count = tabs_before_active.len();
         let right_count = tabs_after_active.len();
 
-        // left_more_tab_index is first tab to the left of the leftmost visible tab
-        let left_more_tab_index = left_count.saturating_sub(1);
+        // left_more_tab_index is first tab to the left of the leftmost visible tab,
+        // taken from the tab itself as the shown tabs may be a filtered subset
+        let left_more_tab_index = tabs_before_active.last().and_then(|t| t.tab_index);
         let collapsed_left = left_more_message(
             left_count,
             palette,
             tab_separator(capabilities),
             left_more_tab_index,
             mode,
+            transparent,
         );
 
         // right_more_tab_index is the first tab to the right of the rightmost visible tab
-        let right_more_tab_index = left_count + tabs_to_render.len();
+        let right_more_tab_index = tabs_after_active.first().and_then(|t| t.tab_index);
         let collapsed_right = right_more_message(
             right_count,
             palette,
             tab_separator(capabilities),
             right_more_tab_index,
             mode,
+            transparent,
         );
 
         let total_size = collapsed_left.len + middle_size + collapsed_right.len;
@@ -113,8 +122,9 @@ fn left_more_message(
     tab_count_to_the_left: usize,
     palette: Palette,
     separator: &str,
-    tab_index: usize,
+    tab_index: Option<usize>,
     mode: InputMode,
+    transparent: bool,
 ) -> LinePart {
     if tab_count_to_the_left == 0 {
         return LinePart::default();
@@ -133,15 +143,18 @@ fn left_more_message(
         (InputMode::Locked, ThemeHue::Light) => (palette.black, palette.white),
         (_, ThemeHue::Light) => (palette.white, palette.black),
     };
-    let left_separator = style!(sep_color, palette.orange).paint(separator);
+    let (left_separator, right_separator) =
+        separator_styles(sep_color, palette.orange, transparent);
+    let left_separator = left_separator.paint(separator);
     let more_styled_text = style!(text_color, palette.orange).bold().paint(more_text);
-    let right_separator = style!(palette.orange, sep_color).paint(separator);
+    let right_separator = right_separator.paint(separator);
     let more_styled_text =
         ANSIStrings(&[left_separator, more_styled_text, right_separator]).to_string();
     LinePart {
         part: more_styled_text,
         len: more_text_len,
-        tab_index: Some(tab_index),
+        tab_index,
+        target: None,
     }
 }
 
@@ -149,8 +162,9 @@ fn right_more_message(
     tab_count_to_the_right: usize,
     palette: Palette,
     separator: &str,
-    tab_index: usize,
+    tab_index: Option<usize>,
     mode: InputMode,
+    transparent: bool,
 ) -> LinePart {
     if tab_count_to_the_right == 0 {
         return LinePart::default();
@@ -168,27 +182,49 @@ fn right_more_message(
         (InputMode::Locked, ThemeHue::Light) => (palette.black, palette.white),
         (_, ThemeHue::Light) => (palette.white, palette.black),
     };
-    let left_separator = style!(sep_color, palette.orange).paint(separator);
+    let (left_separator, right_separator) =
+        separator_styles(sep_color, palette.orange, transparent);
+    let left_separator = left_separator.paint(separator);
     let more_styled_text = style!(text_color, palette.orange).bold().paint(more_text);
-    let right_separator = style!(palette.orange, sep_color).paint(separator);
+    let right_separator = right_separator.paint(separator);
     let more_styled_text =
         ANSIStrings(&[left_separator, more_styled_text, right_separator]).to_string();
     LinePart {
         part: more_styled_text,
         len: more_text_len,
-        tab_index: Some(tab_index),
+        tab_index,
+        target: None,
     }
 }
 
+// per-frame state shown in the prefix, alongside what the config enables
+#[derive(Debug, Default)]
+pub struct PrefixState<'a> {
+    pub other_sessions: &'a [String],
+    pub outdated_zellij: bool,
+    pub away_since: Option<&'a str>,
+    pub mode_menu_open: bool,
+    // the query typed so far, while the tab filter is open
+    pub tab_filter: Option<&'a str>,
+}
+
 fn tab_line_prefix(
     session_name: Option<&str>,
+    state: &PrefixState,
     palette: Palette,
     cols: usize,
+    active_tab_len: usize,
     mode: InputMode,
+    config: &Config,
 ) -> Vec<LinePart> {
-    let prefix_text = " Zellij ".to_string();
+    let prefix_text = config.density.prefix_text();
 
     let prefix_text_len = prefix_text.chars().count();
+    // in a pane too narrow for the label (e.g. during layout changes) the whole
+    // prefix is left out, and tab_line still draws the active tab if it fits
+    if prefix_text_len > cols {
+        return vec![];
+    }
     let text_color = match (mode, palette.theme_hue) {
         (InputMode::Locked, ThemeHue::Dark) => palette.white,
         (_, ThemeHue::Dark) => palette.black,
@@ -201,12 +237,124 @@ fn tab_line_prefix(
         (InputMode::Locked, ThemeHue::Light) => palette.white,
         (_, ThemeHue::Light) => palette.black,
     };
-    let prefix_styled_text = style!(text_color, bg_color).bold().paint(prefix_text);
+    let transparent = config.transparent_background;
+    let prefix_styled_text = on_bar(text_color, bg_color, transparent)
+        .bold()
+        .paint(prefix_text);
     let mut parts = vec![LinePart {
         part: prefix_styled_text.to_string(),
         len: prefix_text_len,
         tab_index: None,
+        target: None,
     }];
+    let mut used_len = prefix_text_len;
+    if config.mode_switcher {
+        // while the menu is open every switchable mode is listed for picking
+        let mode_items: Vec<(String, ClickTarget)> = if state.mode_menu_open {
+            SWITCHABLE_MODES
+                .iter()
+                .map(|m| (format!("[{}] ", mode_name(*m)), ClickTarget::Mode(*m)))
+                .collect()
+        } else {
+            vec![(format!("{} ", mode_name(mode)), ClickTarget::ModeSwitcher)]
+        };
+        for (mode_text, target) in mode_items {
+            let mode_text_len = mode_text.width();
+            if cols.saturating_sub(used_len) < mode_text_len {
+                break;
+            }
+            used_len += mode_text_len;
+            let mode_styled_text = if target == ClickTarget::Mode(mode) {
+                on_bar(palette.green, bg_color, transparent)
+                    .bold()
+                    .paint(mode_text)
+            } else {
+                on_bar(text_color, bg_color, transparent)
+                    .bold()
+                    .paint(mode_text)
+            };
+            parts.push(LinePart {
+                part: mode_styled_text.to_string(),
+                len: mode_text_len,
+                tab_index: None,
+                target: Some(target),
+            });
+        }
+    }
+    if config.tab_filter {
+        // the query being typed, or just the key to start one
+        let filter_text = match state.tab_filter {
+            Some(query) => format!("/{}_ ", query),
+            None => "/ ".to_string(),
+        };
+        let filter_text_len = filter_text.width();
+        if cols.saturating_sub(used_len) >= filter_text_len {
+            used_len += filter_text_len;
+            let filter_color = match state.tab_filter {
+                Some(_) => palette.green,
+                None => text_color,
+            };
+            let filter_styled_text = on_bar(filter_color, bg_color, transparent)
+                .bold()
+                .paint(filter_text);
+            parts.push(LinePart {
+                part: filter_styled_text.to_string(),
+                len: filter_text_len,
+                tab_index: None,
+                target: Some(ClickTarget::TabFilter),
+            });
+        }
+    }
+    if state.outdated_zellij {
+        let warning_text = "⚠ ";
+        let warning_text_len = warning_text.width();
+        if cols.saturating_sub(used_len) >= warning_text_len {
+            used_len += warning_text_len;
+            let warning_styled_text = on_bar(palette.red, bg_color, transparent)
+                .bold()
+                .paint(warning_text);
+            parts.push(LinePart {
+                part: warning_styled_text.to_string(),
+                len: warning_text_len,
+                tab_index: None,
+                target: None,
+            });
+        }
+    }
+    if let Some(time) = state.away_since {
+        let away_text = format!("away since {} ", time);
+        let away_text_len = away_text.width();
+        if cols.saturating_sub(used_len) >= away_text_len {
+            used_len += away_text_len;
+            let away_styled_text = on_bar(palette.orange, bg_color, transparent)
+                .bold()
+                .paint(away_text);
+            parts.push(LinePart {
+                part: away_styled_text.to_string(),
+                len: away_text_len,
+                tab_index: None,
+                target: None,
+            });
+        }
+    }
+    if config.moon_phase {
+        let now = clock::now();
+        let moon_text = match config.moon_illumination {
+            true => format!("{} {}% ", moon::phase_glyph(now), moon::illumination(now)),
+            false => format!("{} ", moon::phase_glyph(now)),
+        };
+        let moon_text_len = moon_text.width();
+        if cols.saturating_sub(used_len) >= moon_text_len {
+            used_len += moon_text_len;
+            let moon_styled_text = on_bar(text_color, bg_color, transparent).paint(moon_text);
+            parts.push(LinePart {
+                part: moon_styled_text.to_string(),
+                len: moon_text_len,
+                tab_index: None,
+                target: None,
+            });
+        }
+    }
     if let Some(name) = session_name {
         let name_part = format!("({}) ", name);
         let name_part_len = name_part.width();
@@ -216,18 +364,90 @@ fn tab_line_prefix(
             (InputMode::Locked, ThemeHue::Light) => palette.black,
             (_, ThemeHue::Light) => palette.white,
         };
-        let name_part_styled_text = style!(text_color, bg_color).bold().paint(name_part);
-        if cols.saturating_sub(prefix_text_len) >= name_part_len {
+        let name_part_styled_text = on_bar(text_color, bg_color, transparent)
+            .bold()
+            .paint(name_part);
+        if cols.saturating_sub(used_len) >= name_part_len {
+            used_len += name_part_len;
             parts.push(LinePart {
                 part: name_part_styled_text.to_string(),
                 len: name_part_len,
                 tab_index: None,
+                target: None,
             })
         }
     }
+    // the session list and shortcut buttons only get what's left after the
+    // active tab, so a long list can't push the tabs off the bar
+    let extras_cols = cols.saturating_sub(active_tab_len);
+    // other live sessions follow the current one and switch to it when clicked
+    for name in state.other_sessions {
+        let session_part = format!("{} ", name);
+        let session_part_len = session_part.width();
+        if extras_cols.saturating_sub(used_len) < session_part_len {
+            break;
+        }
+        used_len += session_part_len;
+        let session_part_styled_text =
+            on_bar(text_color, bg_color, transparent).paint(session_part);
+        parts.push(LinePart {
+            part: session_part_styled_text.to_string(),
+            len: session_part_len,
+            tab_index: None,
+            target: Some(ClickTarget::Session(name.clone())),
+        });
+    }
+    for button in &config.shortcut_buttons {
+        let button_text = format!("[{}] ", button.label());
+        let button_text_len = button_text.width();
+        if extras_cols.saturating_sub(used_len) < button_text_len {
+            break;
+        }
+        used_len += button_text_len;
+        let button_styled_text = on_bar(text_color, bg_color, transparent)
+            .bold()
+            .paint(button_text);
+        parts.push(LinePart {
+            part: button_styled_text.to_string(),
+            len: button_text_len,
+            tab_index: None,
+            target: Some(ClickTarget::Shortcut(*button)),
+        });
+    }
     parts
 }
 
+// text drawn straight on the bar, leaving the background to the terminal when
+// it's transparent
+fn on_bar(fg: PaletteColor, bar_color: PaletteColor, transparent: bool) -> Style {
+    let style = style!(fg, bar_color);
+    match transparent {
+        true => Style {
+            background: None,
+            ..style
+        },
+        false => style,
+    }
+}
+
+// styles for the separators on either side of a segment; with a transparent
+// background their bar side is left to the terminal, the left one by drawing
+// its arrow in reverse
+pub fn separator_styles(
+    bar_color: PaletteColor,
+    segment_color: PaletteColor,
+    transparent: bool,
+) -> (Style, Style) {
+    if transparent {
+        let right = on_bar(segment_color, bar_color, true);
+        return (right.reverse(), right);
+    }
+    (
+        style!(bar_color, segment_color),
+        style!(segment_color, bar_color),
+    )
+}
+
 pub fn tab_separator(capabilities: PluginCapabilities) -> &'static str {
     if !capabilities.arrow_fonts {
         ARROW_SEPARATOR
@@ -239,30 +459,58 @@ pub fn tab_separator(capabilities: PluginCapabilities) -> &'static str {
 #[allow(clippy::too_many_arguments)]
 pub fn tab_line(
     session_name: Option<&str>,
+    prefix_state: &PrefixState,
     mut all_tabs: Vec<LinePart>,
     active_tab_index: usize,
+    rows: usize,
     cols: usize,
     palette: Palette,
     capabilities: PluginCapabilities,
     hide_session_name: bool,
     mode: InputMode,
-) -> Vec<LinePart> {
+    config: &Config,
+) -> Vec<Vec<LinePart>> {
+    let density = config.density;
+    // a stale active index must not make split_off panic
+    let active_tab_index = active_tab_index.min(all_tabs.len().saturating_sub(1));
+    // a prefix on its own row doesn't share it with the tabs
+    let active_tab_len = match rows > 1 {
+        true => 0,
+        false => all_tabs.get(active_tab_index).map_or(0, |tab| tab.len),
+    };
+    let session_name = session_name.filter(|_| !hide_session_name && density.shows_session_name());
+    let prefix = tab_line_prefix(
+        session_name,
+        prefix_state,
+        palette,
+        cols,
+        active_tab_len,
+        mode,
+        config,
+    );
+    // with more than one row available the prefix moves to the second row and
+    // the tabs get the whole first one
+    let (mut line, prefix_row) = if rows > 1 {
+        (vec![], Some(prefix))
+    } else {
+        (prefix, None)
+    };
+    let prefix_len = get_current_title_len(&line);
+
     let mut tabs_after_active = all_tabs.split_off(active_tab_index);
     let mut tabs_before_active = all_tabs;
     let active_tab = if !tabs_after_active.is_empty() {
         tabs_after_active.remove(0)
+    } else if let Some(tab) = tabs_before_active.pop() {
+        tab
     } else {
-        tabs_before_active.pop().unwrap()
+        // no tabs to draw, e.g. when the tab filter matches none of them
+        return [Some(line), prefix_row].into_iter().flatten().collect();
     };
-    let mut prefix = match hide_session_name {
-        true => tab_line_prefix(None, palette, cols, mode),
-        false => tab_line_prefix(session_name, palette, cols, mode),
-    };
-    let prefix_len = get_current_title_len(&prefix);
 
     // if active tab alone won't fit in cols, don't draw any tabs
     if prefix_len + active_tab.len > cols {
-        return prefix;
+        return [Some(line), prefix_row].into_iter().flatten().collect();
     }
 
     let mut tabs_to_render = vec![active_tab];
@@ -275,7 +523,234 @@ pub fn tab_line(
         palette,
         capabilities,
         mode,
+        config.transparent_background,
     );
-    prefix.append(&mut tabs_to_render);
-    prefix
+    let tabs_len = get_current_title_len(&tabs_to_render);
+    let transparent = config.transparent_background;
+    match config.tab_alignment {
+        TabAlignment::Left => line.append(&mut tabs_to_render),
+        TabAlignment::Center => {
+            // center the tabs in the whole line, but never push them into the prefix
+            let padding_len = (cols.saturating_sub(tabs_len) / 2).saturating_sub(prefix_len);
+            if padding_len > 0 {
+                line.push(line_padding(padding_len, palette, mode, transparent));
+            }
+            line.append(&mut tabs_to_render);
+        }
+        TabAlignment::Spread => {
+            // equal gaps between the tabs across the space after the prefix, with
+            // the leftover columns going to the first gaps
+            let free_len = cols.saturating_sub(prefix_len + tabs_len);
+            let gap_count = tabs_to_render.len().saturating_sub(1).max(1);
+            let (gap_len, leftover) = (free_len / gap_count, free_len % gap_count);
+            for (i, tab) in tabs_to_render.into_iter().enumerate() {
+                let padding_len = gap_len + usize::from(i <= leftover);
+                if i > 0 && padding_len > 0 {
+                    line.push(line_padding(padding_len, palette, mode, transparent));
+                }
+                line.push(tab);
+            }
+        }
+    }
+    [Some(line), prefix_row].into_iter().flatten().collect()
+}
+
+fn line_padding(len: usize, palette: Palette, mode: InputMode, transparent: bool) -> LinePart {
+    // every styled part ends with a reset, so unstyled padding shows the terminal background
+    if transparent {
+        return LinePart {
+            part: " ".repeat(len),
+            len,
+            tab_index: None,
+            target: None,
+        };
+    }
+    let (text_color, bg_color) = match (mode, palette.theme_hue) {
+        (InputMode::Locked, ThemeHue::Dark) => (palette.white, palette.black),
+        (_, ThemeHue::Dark) => (palette.black, palette.white),
+        (InputMode::Locked, ThemeHue::Light) => (palette.black, palette.white),
+        (_, ThemeHue::Light) => (palette.white, palette.black),
+    };
+    LinePart {
+        part: style!(text_color, bg_color)
+            .paint(" ".repeat(len))
+            .to_string(),
+        len,
+        tab_index: None,
+        target: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Density;
+    use crate::shortcut::ShortcutButton;
+
+    fn tabs() -> Vec<LinePart> {
+        ["a", "editor", "a much longer tab name", "logs", "編集", "x"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| LinePart {
+                part: name.to_string(),
+                len: name.width() + 2,
+                tab_index: Some(i),
+                target: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rows_never_exceed_the_width() {
+        let other_sessions = ["work".to_string(), "scratch".to_string()];
+        let prefix_state = PrefixState {
+            other_sessions: &other_sessions,
+            outdated_zellij: true,
+            away_since: Some("12:00"),
+            ..PrefixState::default()
+        };
+        for density in [Density::Cozy, Density::Compact, Density::Ultra] {
+            for tab_alignment in [
+                TabAlignment::Left,
+                TabAlignment::Center,
+                TabAlignment::Spread,
+            ] {
+                let config = Config {
+                    density,
+                    tab_alignment,
+                    mode_switcher: true,
+                    ..Config::default()
+                };
+                for rows in [1, 2] {
+                    for active_tab_index in [0, 2, 5] {
+                        for cols in 0..200 {
+                            let line = tab_line(
+                                Some("session"),
+                                &prefix_state,
+                                tabs(),
+                                active_tab_index,
+                                rows,
+                                cols,
+                                Palette::default(),
+                                PluginCapabilities::default(),
+                                false,
+                                InputMode::Normal,
+                                &config,
+                            );
+                            for row in &line {
+                                assert!(
+                                    get_current_title_len(row) <= cols,
+                                    "{:?} {:?} rows {} active {} cols {}",
+                                    density,
+                                    tab_alignment,
+                                    rows,
+                                    active_tab_index,
+                                    cols
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn no_tabs_leaves_only_the_prefix() {
+        for rows in [1, 2] {
+            let line = tab_line(
+                Some("session"),
+                &PrefixState::default(),
+                vec![],
+                0,
+                rows,
+                80,
+                Palette::default(),
+                PluginCapabilities::default(),
+                false,
+                InputMode::Normal,
+                &Config::default(),
+            );
+            let tab_count = line
+                .iter()
+                .flatten()
+                .filter(|p| p.tab_index.is_some())
+                .count();
+            assert_eq!(tab_count, 0);
+            assert_eq!(line.len(), rows);
+        }
+    }
+
+    #[test]
+    fn extras_leave_room_for_the_active_tab() {
+        let many_sessions: Vec<String> = (0..40).map(|i| format!("session-{}", i)).collect();
+        let config = Config {
+            shortcut_buttons: vec![
+                ShortcutButton::NewTab,
+                ShortcutButton::NewPane,
+                ShortcutButton::Detach,
+                ShortcutButton::Lock,
+            ],
+            ..Config::default()
+        };
+        for other_sessions in [&many_sessions[..], &[]] {
+            let prefix_state = PrefixState {
+                other_sessions,
+                ..PrefixState::default()
+            };
+            for active_tab_index in [0, 2, 5] {
+                for cols in [50, 80, 120] {
+                    let line = tab_line(
+                        Some("session"),
+                        &prefix_state,
+                        tabs(),
+                        active_tab_index,
+                        1,
+                        cols,
+                        Palette::default(),
+                        PluginCapabilities::default(),
+                        false,
+                        InputMode::Normal,
+                        &config,
+                    );
+                    assert!(line[0]
+                        .iter()
+                        .any(|part| part.tab_index == Some(active_tab_index)));
+                    assert!(get_current_title_len(&line[0]) <= cols);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn transparent_prefix_leaves_the_background_alone() {
+        let other_sessions = ["work".to_string()];
+        let prefix_state = PrefixState {
+            other_sessions: &other_sessions,
+            outdated_zellij: true,
+            ..PrefixState::default()
+        };
+        for transparent_background in [false, true] {
+            let config = Config {
+                transparent_background,
+                ..Config::default()
+            };
+            let prefix = tab_line_prefix(
+                Some("session"),
+                &prefix_state,
+                Palette::default(),
+                80,
+                0,
+                InputMode::Normal,
+                &config,
+            );
+            let painted = prefix.iter().any(|part| part.part.contains("48;"));
+            assert_eq!(painted, !transparent_background);
+        }
+        let (left, right) =
+            separator_styles(PaletteColor::EightBit(7), PaletteColor::EightBit(2), true);
+        assert_eq!(right.background, None);
+        assert_eq!(left.background, None);
+        assert!(left.is_reverse);
+    }
 }
@@ -1,21 +1,31 @@
+mod clock;
+mod config;
+mod filter;
+mod frame_log;
+mod hitbox;
 mod line;
+mod moon;
+mod shortcut;
 mod tab;
 
 use std::cmp::{max, min};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
 
-use tab::get_tab_to_focus;
 use zellij_tile::prelude::*;
 
-use crate::line::tab_line;
-use crate::tab::tab_style;
+use crate::config::Config;
+use crate::hitbox::{hitboxes, target_at, ClickTarget, Hitbox};
+use crate::line::{tab_line, PrefixState};
+use crate::tab::{format_tab_name, tab_style};
 
 #[derive(Debug, Default)]
 pub struct LinePart {
     part: String,
     len: usize,
     tab_index: Option<usize>,
+    // what clicking this part does, for parts that aren't tabs
+    target: Option<ClickTarget>,
 }
 
 #[derive(Default)]
@@ -23,26 +33,92 @@ struct State {
     tabs: Vec<TabInfo>,
     active_tab_idx: usize,
     mode_info: ModeInfo,
-    tab_line: Vec<LinePart>,
+    hitboxes: Vec<Hitbox>,
+    config: Config,
+    last_logged_frame: String,
+    outdated_zellij: bool,
+    other_sessions: Vec<String>,
+    // terminal pane count by tab position
+    pane_counts: HashMap<usize, usize>,
+    // unix timestamps of the last input and of when the session went idle
+    last_input: u64,
+    away_since: Option<u64>,
+    mode_menu_open: bool,
+    // the query typed so far while the tab filter is open
+    tab_filter: Option<String>,
 }
 
 static ARROW_SEPARATOR: &str = "";
 
+// the zellij-tile release this plugin is built against
+static MIN_ZELLIJ_VERSION: &str = "0.39.0";
+
+// compares dotted numeric versions component by component, ignoring any
+// pre-release suffix and treating missing components as 0, so "0.39" is not
+// older than "0.39.0"; a version that doesn't parse is never reported as older
+fn is_older_version(version: &str, minimum: &str) -> bool {
+    let parse = |v: &str| -> Option<Vec<u32>> {
+        v.split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|n| n.parse().ok())
+            .collect()
+    };
+    let (Some(version), Some(minimum)) = (parse(version), parse(minimum)) else {
+        return false;
+    };
+    for i in 0..version.len().max(minimum.len()) {
+        let (v, m) = (
+            version.get(i).copied().unwrap_or(0),
+            minimum.get(i).copied().unwrap_or(0),
+        );
+        if v != m {
+            return v < m;
+        }
+    }
+    false
+}
+
+// seconds between idle checks when away detection is enabled
+const AWAY_CHECK_INTERVAL: f64 = 30.0;
+
 register_plugin!(State);
 
+impl State {
+    // key events only reach the plugin while its pane has focus, so the bar is
+    // made selectable for as long as the filter is open
+    fn open_tab_filter(&mut self) {
+        self.tab_filter = Some(String::new());
+        set_selectable(true);
+        focus_plugin_pane(get_plugin_ids().plugin_id, false);
+    }
+
+    fn close_tab_filter(&mut self) {
+        self.tab_filter = None;
+        set_selectable(false);
+    }
+}
+
 impl ZellijPlugin for State {
-    fn load(&mut self, _configuration: BTreeMap<String, String>) {
-        subscribe(&[
-            EventType::PermissionRequestResult,
-            EventType::TabUpdate,
-            EventType::ModeUpdate,
-            EventType::Mouse,
-        ]);
-        request_permission(&[
-            PermissionType::ReadApplicationState,
-            PermissionType::ChangeApplicationState,
-            PermissionType::RunCommands,
-        ]);
+    fn load(&mut self, configuration: BTreeMap<String, String>) {
+        self.config = Config::new(&configuration);
+        subscribe(&self.config.event_types());
+        request_permission(&self.config.permissions());
+
+        let zellij_version = get_zellij_version();
+        if is_older_version(&zellij_version, MIN_ZELLIJ_VERSION) {
+            eprintln!(
+                "Running on zellij {} but this plugin targets zellij {} or newer.",
+                zellij_version, MIN_ZELLIJ_VERSION
+            );
+            self.outdated_zellij = true;
+        }
+
+        if self.config.away_after > 0 {
+            self.last_input = clock::now();
+            set_timeout(AWAY_CHECK_INTERVAL);
+        }
     }
 
     fn update(&mut self, event: Event) -> bool {
@@ -50,6 +126,7 @@ impl ZellijPlugin for State {
         match event {
             Event::PermissionRequestResult(_) => {
                 set_selectable(false);
+                unsubscribe(&[EventType::PermissionRequestResult]);
             }
             Event::ModeUpdate(mode_info) => {
                 if self.mode_info != mode_info {
@@ -71,11 +148,111 @@ impl ZellijPlugin for State {
                     eprintln!("Could not find active tab.");
                 }
             }
+            Event::SessionUpdate(sessions, _) => {
+                let other_sessions: Vec<String> = sessions
+                    .into_iter()
+                    .filter(|s| !s.is_current_session)
+                    .map(|s| s.name)
+                    .collect();
+                if self.other_sessions != other_sessions {
+                    should_render = true;
+                }
+                self.other_sessions = other_sessions;
+            }
+            Event::PaneUpdate(pane_manifest) => {
+                let pane_counts: HashMap<usize, usize> = pane_manifest
+                    .panes
+                    .iter()
+                    .map(|(tab_position, panes)| {
+                        (*tab_position, panes.iter().filter(|p| !p.is_plugin).count())
+                    })
+                    .collect();
+                if self.pane_counts != pane_counts {
+                    should_render = true;
+                }
+                self.pane_counts = pane_counts;
+            }
+            Event::InputReceived => {
+                self.last_input = clock::now();
+                if self.away_since.take().is_some() {
+                    should_render = true;
+                }
+            }
+            Event::Timer(_) => {
+                let idle_secs = clock::now().saturating_sub(self.last_input);
+                if self.away_since.is_none()
+                    && idle_secs >= self.config.away_after.saturating_mul(60)
+                {
+                    self.away_since = Some(self.last_input);
+                    should_render = true;
+                }
+                set_timeout(AWAY_CHECK_INTERVAL);
+            }
+            Event::Key(key) => {
+                if let Some(query) = self.tab_filter.as_mut() {
+                    match key {
+                        Key::Char('\n') => {
+                            if let Some(tab) = filter::best_match(&self.tabs, query) {
+                                switch_tab_to(tab.position as u32 + 1);
+                            }
+                            self.close_tab_filter();
+                        }
+                        Key::Esc => self.close_tab_filter(),
+                        Key::Backspace => {
+                            query.pop();
+                        }
+                        // control characters (e.g. Tab arrives as '\t') have no width and
+                        // would throw off the layout after the filter segment
+                        Key::Char(c) if !c.is_control() => query.push(c),
+                        _ => {}
+                    }
+                    should_render = true;
+                }
+            }
             Event::Mouse(me) => match me {
-                Mouse::LeftClick(_, col) => {
-                    let tab_to_focus = get_tab_to_focus(&self.tab_line, self.active_tab_idx, col);
-                    if let Some(idx) = tab_to_focus {
-                        switch_tab_to(idx.try_into().unwrap());
+                Mouse::LeftClick(line, col) => {
+                    let row = usize::try_from(line).unwrap_or(usize::MAX);
+                    match target_at(&self.hitboxes, row, col) {
+                        Some(ClickTarget::Tab(tab_index)) => {
+                            // tabs are indexed starting from 1 so we need to add 1
+                            let clicked_tab_idx = tab_index + 1;
+                            if clicked_tab_idx != self.active_tab_idx {
+                                switch_tab_to(clicked_tab_idx.try_into().unwrap());
+                            }
+                            // picking a tab by mouse ends the filter, as Enter would
+                            if self.tab_filter.is_some() {
+                                self.close_tab_filter();
+                                should_render = true;
+                            }
+                        }
+                        Some(ClickTarget::Session(name)) => switch_session(Some(name)),
+                        Some(ClickTarget::Shortcut(button)) => button.trigger(),
+                        Some(ClickTarget::ModeSwitcher) => {
+                            switch_to_input_mode(&self.config.next_mode(self.mode_info.mode));
+                        }
+                        Some(ClickTarget::Mode(mode)) => {
+                            switch_to_input_mode(mode);
+                            self.mode_menu_open = false;
+                            should_render = true;
+                        }
+                        Some(ClickTarget::TabFilter) => {
+                            if self.tab_filter.is_some() {
+                                self.close_tab_filter();
+                            } else {
+                                self.open_tab_filter();
+                            }
+                            should_render = true;
+                        }
+                        None => {}
+                    }
+                }
+                Mouse::RightClick(line, col) => {
+                    let row = usize::try_from(line).unwrap_or(usize::MAX);
+                    if let Some(ClickTarget::ModeSwitcher | ClickTarget::Mode(_)) =
+                        target_at(&self.hitboxes, row, col)
+                    {
+                        self.mode_menu_open = !self.mode_menu_open;
+                        should_render = true;
                     }
                 }
                 Mouse::ScrollUp(_) => {
@@ -93,49 +270,105 @@ impl ZellijPlugin for State {
         should_render
     }
 
-    fn render(&mut self, _rows: usize, cols: usize) {
+    fn render(&mut self, rows: usize, cols: usize) {
         if self.tabs.is_empty() {
             return;
         }
+        let palette = self.config.palette.apply(self.mode_info.style.colors);
         let mut all_tabs: Vec<LinePart> = vec![];
         let mut active_tab_index = 0;
         let mut is_alternate_tab = false;
-        for t in &mut self.tabs {
+        // while filtering, only matching tabs are shown and the best match is
+        // drawn as the active one, as that's where Enter jumps to
+        let shown_tabs: Vec<TabInfo> = match self.tab_filter.as_deref() {
+            Some(query) => {
+                let best_position = filter::best_match(&self.tabs, query).map(|t| t.position);
+                self.tabs
+                    .iter()
+                    .filter(|t| filter::fuzzy_score(&t.name, query).is_some())
+                    .map(|t| TabInfo {
+                        active: Some(t.position) == best_position,
+                        ..t.clone()
+                    })
+                    .collect()
+            }
+            None => self.tabs.clone(),
+        };
+        for (i, t) in shown_tabs.iter().enumerate() {
             let mut tabname = t.name.clone();
             if t.active && self.mode_info.mode == InputMode::RenameTab {
                 if tabname.is_empty() {
                     tabname = String::from("Enter name...");
                 }
-                active_tab_index = t.position;
+                active_tab_index = i;
             } else if t.active {
-                active_tab_index = t.position;
+                active_tab_index = i;
             }
+            let tabname = format_tab_name(
+                &self.config.tab_name_format,
+                &tabname,
+                t,
+                self.pane_counts.get(&t.position).copied().unwrap_or(0),
+                self.config
+                    .tab_marks
+                    .get(&t.name)
+                    .map(String::as_str)
+                    .unwrap_or(""),
+            );
             let tab = tab_style(
                 tabname,
                 t,
                 is_alternate_tab,
-                self.mode_info.style.colors,
+                palette,
                 self.mode_info.capabilities,
                 self.mode_info.mode,
+                self.config.tab_colors.accent(&t.name, palette),
+                &self.config,
             );
             is_alternate_tab = !is_alternate_tab;
             all_tabs.push(tab);
         }
-        self.tab_line = tab_line(
+        let away_since = self
+            .away_since
+            .map(|timestamp| clock::format_hh_mm(timestamp, self.config.utc_offset));
+        let prefix_state = PrefixState {
+            other_sessions: &self.other_sessions,
+            outdated_zellij: self.outdated_zellij,
+            away_since: away_since.as_deref(),
+            mode_menu_open: self.mode_menu_open,
+            tab_filter: self.tab_filter.as_deref(),
+        };
+        let tab_line = tab_line(
             self.mode_info.session_name.as_deref(),
+            &prefix_state,
             all_tabs,
             active_tab_index,
+            rows,
             cols.saturating_sub(1),
-            self.mode_info.style.colors,
+            palette,
             self.mode_info.capabilities,
             self.mode_info.style.hide_session_name,
             self.mode_info.mode,
+            &self.config,
         );
 
-        let output = self
-            .tab_line
+        self.hitboxes = hitboxes(&tab_line);
+
+        let output: Vec<String> = tab_line
             .iter()
-            .fold(String::new(), |output, part| output + &part.part);
+            .map(|line| {
+                line.iter()
+                    .fold(String::new(), |output, part| output + &part.part)
+            })
+            .collect();
+
+        if self.config.frame_log {
+            let frame = frame_log::strip_ansi(&output.join(" | "));
+            if frame != self.last_logged_frame {
+                frame_log::append_frame(&frame, self.config.frame_log_max_size);
+                self.last_logged_frame = frame;
+            }
+        }
 
         let background = match (self.mode_info.mode, self.mode_info.style.colors.theme_hue) {
             (InputMode::Locked, ThemeHue::Dark) => self.mode_info.style.colors.black,
@@ -143,13 +376,42 @@ impl ZellijPlugin for State {
             (InputMode::Locked, ThemeHue::Light) => self.mode_info.style.colors.white,
             (_, ThemeHue::Light) => self.mode_info.style.colors.black,
         };
-        match background {
-            PaletteColor::Rgb((r, g, b)) => {
-                print!("{}\u{1b}[48;2;{};{};{}m\u{1b}[0K", output, r, g, b);
-            }
-            PaletteColor::EightBit(color) => {
-                print!("{}\u{1b}[48;5;{}m\u{1b}[0K", output, color);
-            }
-        }
+        let fill = match background {
+            _ if self.config.transparent_background => "\u{1b}[49m\u{1b}[0K".to_string(),
+            PaletteColor::Rgb((r, g, b)) => format!("\u{1b}[48;2;{};{};{}m\u{1b}[0K", r, g, b),
+            PaletteColor::EightBit(color) => format!("\u{1b}[48;5;{}m\u{1b}[0K", color),
+        };
+        let rendered_rows: Vec<String> = output
+            .iter()
+            .map(|line| format!("{}{}", line, fill))
+            .collect();
+        print!("{}", rendered_rows.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_older_version;
+
+    #[test]
+    fn missing_components_count_as_zero() {
+        assert!(!is_older_version("0.39", "0.39.0"));
+        assert!(!is_older_version("0.39.0", "0.39"));
+        assert!(is_older_version("0.38", "0.39.0"));
+    }
+
+    #[test]
+    fn components_compare_numerically() {
+        assert!(is_older_version("0.9.0", "0.39.0"));
+        assert!(!is_older_version("0.40.0", "0.39.0"));
+        assert!(!is_older_version("1.0.0", "0.39.0"));
+        assert!(!is_older_version("0.39.1-rc1", "0.39.0"));
+        assert!(is_older_version("0.38.2+build", "0.39.0"));
+    }
+
+    #[test]
+    fn unparsable_versions_are_not_older() {
+        assert!(!is_older_version("", "0.39.0"));
+        assert!(!is_older_version("0.x.0", "0.39.0"));
     }
 }
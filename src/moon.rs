@@ -0,0 +1,63 @@
+use std::f64::consts::PI;
+
+const SYNODIC_MONTH_SECS: f64 = 29.530_588_853 * 86_400.0;
+// new moon of 2000-01-06 18:14 UTC
+const REFERENCE_NEW_MOON: f64 = 947_182_440.0;
+const PHASE_GLYPHS: [&str; 8] = ["🌑", "🌒", "🌓", "🌔", "🌕", "🌖", "🌗", "🌘"];
+
+// fraction of the lunar cycle elapsed at the given unix timestamp, 0 being new moon
+fn cycle_position(timestamp: u64) -> f64 {
+    ((timestamp as f64 - REFERENCE_NEW_MOON) / SYNODIC_MONTH_SECS).rem_euclid(1.0)
+}
+
+pub fn phase_glyph(timestamp: u64) -> &'static str {
+    let phase = (cycle_position(timestamp) * 8.0).round() as usize % 8;
+    PHASE_GLYPHS[phase]
+}
+
+// percentage of the disc that is lit
+pub fn illumination(timestamp: u64) -> u8 {
+    let lit = (1.0 - (2.0 * PI * cycle_position(timestamp)).cos()) / 2.0;
+    (lit * 100.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEW_MOON: u64 = REFERENCE_NEW_MOON as u64;
+
+    #[test]
+    fn new_moon_is_dark() {
+        assert_eq!(phase_glyph(NEW_MOON), "🌑");
+        assert_eq!(illumination(NEW_MOON), 0);
+        // a whole cycle later it's new again
+        let next_new_moon = NEW_MOON + SYNODIC_MONTH_SECS as u64;
+        assert_eq!(phase_glyph(next_new_moon), "🌑");
+        assert_eq!(illumination(next_new_moon), 0);
+    }
+
+    #[test]
+    fn full_moon_is_half_a_cycle_later() {
+        let full_moon = NEW_MOON + (SYNODIC_MONTH_SECS / 2.0) as u64;
+        assert_eq!(phase_glyph(full_moon), "🌕");
+        assert_eq!(illumination(full_moon), 100);
+    }
+
+    #[test]
+    fn quarters_are_half_lit() {
+        let first_quarter = NEW_MOON + (SYNODIC_MONTH_SECS / 4.0) as u64;
+        let last_quarter = NEW_MOON + (SYNODIC_MONTH_SECS * 3.0 / 4.0) as u64;
+        assert_eq!(phase_glyph(first_quarter), "🌓");
+        assert_eq!(phase_glyph(last_quarter), "🌗");
+        assert_eq!(illumination(first_quarter), 50);
+        assert_eq!(illumination(last_quarter), 50);
+    }
+
+    #[test]
+    fn timestamps_before_the_reference_wrap() {
+        let full_moon_before = NEW_MOON - (SYNODIC_MONTH_SECS / 2.0) as u64;
+        assert_eq!(phase_glyph(full_moon_before), "🌕");
+        assert_eq!(illumination(full_moon_before), 100);
+    }
+}
@@ -0,0 +1,40 @@
+use zellij_tile::prelude::*;
+
+// zellij actions that can be triggered from buttons in the bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortcutButton {
+    NewTab,
+    NewPane,
+    Detach,
+    Lock,
+}
+
+impl ShortcutButton {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "new_tab" => Some(ShortcutButton::NewTab),
+            "new_pane" => Some(ShortcutButton::NewPane),
+            "detach" => Some(ShortcutButton::Detach),
+            "lock" => Some(ShortcutButton::Lock),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ShortcutButton::NewTab => "+tab",
+            ShortcutButton::NewPane => "+pane",
+            ShortcutButton::Detach => "detach",
+            ShortcutButton::Lock => "lock",
+        }
+    }
+
+    pub fn trigger(self) {
+        match self {
+            ShortcutButton::NewTab => new_tab(),
+            ShortcutButton::NewPane => open_terminal("."),
+            ShortcutButton::Detach => detach(),
+            ShortcutButton::Lock => switch_to_input_mode(&InputMode::Locked),
+        }
+    }
+}
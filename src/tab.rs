@@ -1,4 +1,6 @@
-use crate::{line::tab_separator, LinePart};
+use crate::config::{ActiveTabStyle, Config};
+use crate::line::{separator_styles, tab_separator};
+use crate::LinePart;
 use ansi_term::{ANSIString, ANSIStrings};
 use unicode_width::UnicodeWidthStr;
 use zellij_tile::prelude::*;
@@ -17,6 +19,7 @@ fn cursors(focused_clients: &[ClientId], palette: Palette) -> (Vec<ANSIString>,
     (cursors, len)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn render_tab(
     text: String,
     tab: &TabInfo,
@@ -24,8 +27,15 @@ pub fn render_tab(
     palette: Palette,
     separator: &str,
     mode: InputMode,
+    accent: Option<PaletteColor>,
+    config: &Config,
 ) -> LinePart {
-    let focused_clients = tab.other_focused_clients.as_slice();
+    let density = config.density;
+    let focused_clients = if density.shows_other_clients() {
+        tab.other_focused_clients.as_slice()
+    } else {
+        &[]
+    };
     let separator_width = separator.width();
     let alternate_tab_color = match (mode, palette.theme_hue) {
         // TODO: only do this if we don't have the arrow capabilities
@@ -34,7 +44,9 @@ pub fn render_tab(
         (InputMode::Locked, ThemeHue::Light) => palette.black,
         (_, ThemeHue::Light) => palette.white,
     };
-    let background_color = if tab.active {
+    // an underlined active tab keeps the regular tab background
+    let underline_active = tab.active && config.active_tab_style == ActiveTabStyle::Underline;
+    let background_color = if tab.active && !underline_active {
         palette.green
     } else if is_alternate_tab {
         alternate_tab_color
@@ -50,13 +62,39 @@ pub fn render_tab(
         (InputMode::Locked, ThemeHue::Light) => palette.white,
         (_, ThemeHue::Light) => palette.black,
     };
-    let left_separator = style!(foreground_color, background_color).paint(separator);
-    let mut tab_text_len = text.width() + (separator_width * 2) + 2; // +2 for padding
-    let tab_styled_text = style!(foreground_color, background_color)
-        .bold()
-        .paint(format!(" {} ", text));
+    let text_style = if underline_active {
+        style!(palette.green, background_color).bold().underline()
+    } else {
+        style!(foreground_color, background_color).bold()
+    };
+    let (left_separator, right_separator) = separator_styles(
+        foreground_color,
+        background_color,
+        config.transparent_background,
+    );
+    let left_separator = left_separator.paint(separator);
+    let tab_padding = density.tab_padding();
+    let mut tab_text_len = text.width() + (separator_width * 2) + 2 * tab_padding;
+    let padding = " ".repeat(tab_padding);
+    let tab_styled_text = match accent {
+        // the accent marker takes the place of one column of left padding, if there is any
+        Some(accent_color) => {
+            if tab_padding == 0 {
+                tab_text_len += 1;
+            }
+            let left_padding = " ".repeat(tab_padding.saturating_sub(1));
+            ANSIStrings(&[
+                style!(accent_color, background_color).paint("▎"),
+                text_style.paint(format!("{}{}{}", left_padding, text, padding)),
+            ])
+            .to_string()
+        }
+        None => text_style
+            .paint(format!("{}{}{}", padding, text, padding))
+            .to_string(),
+    };
 
-    let right_separator = style!(background_color, foreground_color).paint(separator);
+    let right_separator = right_separator.paint(separator);
     let tab_styled_text = if !focused_clients.is_empty() {
         let (cursor_section, extra_length) = cursors(focused_clients, palette);
         tab_text_len += extra_length;
@@ -71,69 +109,71 @@ pub fn render_tab(
             .paint("]")
             .to_string();
         s.push_str(&left_separator.to_string());
-        s.push_str(&tab_styled_text.to_string());
+        s.push_str(&tab_styled_text);
         s.push_str(&cursor_beginning);
         s.push_str(&cursor_section);
         s.push_str(&cursor_end);
         s.push_str(&right_separator.to_string());
         s
     } else {
-        ANSIStrings(&[left_separator, tab_styled_text, right_separator]).to_string()
+        format!("{}{}{}", left_separator, tab_styled_text, right_separator)
     };
 
     LinePart {
         part: tab_styled_text,
         len: tab_text_len,
         tab_index: Some(tab.position),
+        target: None,
     }
 }
 
+// name is substituted last so that braces in a tab's own name are left alone
+pub fn format_tab_name(
+    format: &str,
+    name: &str,
+    tab: &TabInfo,
+    pane_count: usize,
+    mark: &str,
+) -> String {
+    let sync = if tab.is_sync_panes_active {
+        " (Sync)"
+    } else {
+        ""
+    };
+    format
+        .replace("{index}", &(tab.position + 1).to_string())
+        .replace("{panes}", &pane_count.to_string())
+        .replace("{sync}", sync)
+        .replace("{mark}", mark)
+        .replace("{name}", name)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn tab_style(
-    mut tabname: String,
+    tabname: String,
     tab: &TabInfo,
     mut is_alternate_tab: bool,
     palette: Palette,
     capabilities: PluginCapabilities,
     mode: InputMode,
+    accent: Option<PaletteColor>,
+    config: &Config,
 ) -> LinePart {
     let separator = tab_separator(capabilities);
 
-    if tab.is_sync_panes_active {
-        tabname.push_str(" (Sync)");
-    }
     // we only color alternate tabs differently if we can't use the arrow fonts to separate them
     if !capabilities.arrow_fonts {
         is_alternate_tab = false;
     }
 
-    render_tab(tabname, tab, is_alternate_tab, palette, separator, mode)
-}
-
-pub(crate) fn get_tab_to_focus(
-    tab_line: &[LinePart],
-    active_tab_idx: usize,
-    mouse_click_col: usize,
-) -> Option<usize> {
-    let clicked_line_part = get_clicked_line_part(tab_line, mouse_click_col)?;
-    let clicked_tab_idx = clicked_line_part.tab_index?;
-    // tabs are indexed starting from 1 so we need to add 1
-    let clicked_tab_idx = clicked_tab_idx + 1;
-    if clicked_tab_idx != active_tab_idx {
-        return Some(clicked_tab_idx);
-    }
-    None
-}
-
-pub(crate) fn get_clicked_line_part(
-    tab_line: &[LinePart],
-    mouse_click_col: usize,
-) -> Option<&LinePart> {
-    let mut len = 0;
-    for tab_line_part in tab_line {
-        if mouse_click_col >= len && mouse_click_col < len + tab_line_part.len {
-            return Some(tab_line_part);
-        }
-        len += tab_line_part.len;
-    }
-    None
+    render_tab(
+        tabname,
+        tab,
+        is_alternate_tab,
+        palette,
+        separator,
+        mode,
+        accent,
+        config,
+    )
 }